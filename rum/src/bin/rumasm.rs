@@ -0,0 +1,37 @@
+use rum::rumasm;
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let input_path = args.next();
+    let output_path = args.next();
+
+    let source = match &input_path {
+        Some(path) => fs::read_to_string(path).unwrap(),
+        None => {
+            let mut buf = String::new();
+            io::stdin().read_to_string(&mut buf).unwrap();
+            buf
+        }
+    };
+
+    let words = match rumasm::assemble(&source) {
+        Ok(words) => words,
+        Err(err) => {
+            eprintln!("rumasm: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let mut bytes = Vec::with_capacity(words.len() * 4);
+    for word in words {
+        bytes.extend_from_slice(&word.to_be_bytes());
+    }
+
+    match output_path {
+        Some(path) => fs::write(path, &bytes).unwrap(),
+        None => io::stdout().write_all(&bytes).unwrap(),
+    }
+}
@@ -0,0 +1,135 @@
+use crate::rumdecoder;
+use crate::rumdisasm::disassemble_one;
+use crate::states::State;
+use crate::trap::Trap;
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+
+/// Decodes and executes exactly one instruction, then returns, unlike
+/// `rumdecoder::run` which loops until a trap.
+///
+/// <br>
+///
+/// # Argument:
+/// * `state`: The struct representing the state of the UM.
+pub fn step(state: &mut State) -> Result<(), Trap> {
+    rumdecoder::decode_inst(state)
+}
+
+/// A breakpoint-aware stepping debugger for the Universal Machine.
+///
+/// <br>
+///
+/// Breakpoints are keyed on the program counter. `Debugger::repl` drives
+/// an interactive session over stdin/stdout with `continue`, `step`,
+/// `break <pc>`, `print r<n>` and `dump <seg>` commands, showing the
+/// disassembly of the instruction about to execute before each prompt.
+pub struct Debugger {
+    breakpoints: BTreeSet<usize>,
+}
+
+impl Debugger {
+    /// Creates a debugger with no breakpoints set.
+    pub fn new() -> Self {
+        Self {
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    /// Sets a breakpoint at the given program counter.
+    pub fn break_at(&mut self, pc: usize) {
+        self.breakpoints.insert(pc);
+    }
+
+    /// Removes a breakpoint previously set with `break_at`.
+    pub fn clear_breakpoint(&mut self, pc: usize) {
+        self.breakpoints.remove(&pc);
+    }
+
+    /// Tells whether a breakpoint is set at the given program counter.
+    pub fn is_breakpoint(&self, pc: usize) -> bool {
+        self.breakpoints.contains(&pc)
+    }
+
+    /// Steps `state` until it reaches a set breakpoint or traps.
+    ///
+    /// <br>
+    ///
+    /// # Argument:
+    /// * `state`: The struct representing the state of the UM.
+    pub fn run_to_breakpoint(&mut self, state: &mut State) -> Result<(), Trap> {
+        loop {
+            step(state)?;
+            if self.breakpoints.contains(&state.prog_counter()) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Drives an interactive debugging session over stdin/stdout.
+    /// Returns once stdin closes or `state` traps.
+    ///
+    /// <br>
+    ///
+    /// # Argument:
+    /// * `state`: The struct representing the state of the UM.
+    pub fn repl(&mut self, state: &mut State) -> Result<(), Trap> {
+        let stdin = io::stdin();
+        loop {
+            self.print_next_instruction(state);
+            print!("(um-dbg) ");
+            io::stdout().flush().ok();
+
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+                return Ok(());
+            }
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("continue") | Some("c") => self.run_to_breakpoint(state)?,
+                Some("step") | Some("s") => step(state)?,
+                Some("break") => match words.next().and_then(|pc| pc.parse().ok()) {
+                    Some(pc) => self.break_at(pc),
+                    None => println!("usage: break <pc>"),
+                },
+                Some("print") => match Self::parse_register(words.next()) {
+                    Some(reg) => println!("r{} = {}", reg, state.register(reg)),
+                    None => println!("usage: print r<n>"),
+                },
+                Some("dump") => match words.next().and_then(|seg| seg.parse().ok()) {
+                    Some(seg) => match state.segment(seg) {
+                        Some(words) => println!("{:?}", words),
+                        None => println!("segment {} is not mapped", seg),
+                    },
+                    None => println!("usage: dump <seg>"),
+                },
+                Some(other) => println!("unknown command: {}", other),
+                None => {}
+            }
+        }
+    }
+
+    fn parse_register(token: Option<&str>) -> Option<u32> {
+        let reg: u32 = token?.strip_prefix('r')?.parse().ok()?;
+        if reg < 8 {
+            Some(reg)
+        } else {
+            None
+        }
+    }
+
+    fn print_next_instruction(&self, state: &State) {
+        if let Some(word) = state
+            .segment(0)
+            .and_then(|seg| seg.get(state.prog_counter()).copied())
+        {
+            println!("{:04x}: {}", state.prog_counter(), disassemble_one(word));
+        }
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,187 @@
+use crate::rumdecoder::{get, Opcode, OP, RA, RB, RC, RL, VL};
+use crate::states::State;
+use crate::trap::Trap;
+use std::convert::TryFrom;
+
+/// Selects how `rumdecoder::run` dispatches instructions.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    /// Re-decode every code word on every fetch (the original behavior).
+    #[default]
+    Interpret,
+    /// Decode each segment once into a flat `DecodedInst` cache and
+    /// dispatch on the cheap tag instead of re-extracting bit fields.
+    PreDecoded,
+    /// Dispatches every segment through the same pre-decoded cache as
+    /// `PreDecoded`; no basic block is lowered to native code.
+    #[cfg(feature = "jit")]
+    Jit,
+}
+
+/// A code word with its opcode and operand fields already extracted, so
+/// the hot loop can dispatch on a cheap tag instead of re-running `get`
+/// and `Opcode::try_from` on every fetch.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum DecodedInst {
+    CMov { a: u32, b: u32, c: u32 },
+    SegLoad { a: u32, b: u32, c: u32 },
+    SegStore { a: u32, b: u32, c: u32 },
+    Add { a: u32, b: u32, c: u32 },
+    Mul { a: u32, b: u32, c: u32 },
+    Div { a: u32, b: u32, c: u32 },
+    BNand { a: u32, b: u32, c: u32 },
+    Halt,
+    MapSeg { b: u32, c: u32 },
+    UnmapSeg { c: u32 },
+    Output { c: u32 },
+    Input { c: u32 },
+    LoadProg { b: u32, c: u32 },
+    LoadVal { location: u32, value: u32 },
+    /// The opcode field did not name one of the fourteen instructions;
+    /// carries the raw word, whose opcode field the fault reports.
+    Data(u32),
+}
+
+pub(crate) fn decode_word(word: u32) -> DecodedInst {
+    match Opcode::try_from(get(&OP, word)) {
+        Ok(Opcode::CMov) => DecodedInst::CMov {
+            a: get(&RA, word),
+            b: get(&RB, word),
+            c: get(&RC, word),
+        },
+        Ok(Opcode::SegLoad) => DecodedInst::SegLoad {
+            a: get(&RA, word),
+            b: get(&RB, word),
+            c: get(&RC, word),
+        },
+        Ok(Opcode::SegStore) => DecodedInst::SegStore {
+            a: get(&RA, word),
+            b: get(&RB, word),
+            c: get(&RC, word),
+        },
+        Ok(Opcode::Add) => DecodedInst::Add {
+            a: get(&RA, word),
+            b: get(&RB, word),
+            c: get(&RC, word),
+        },
+        Ok(Opcode::Mul) => DecodedInst::Mul {
+            a: get(&RA, word),
+            b: get(&RB, word),
+            c: get(&RC, word),
+        },
+        Ok(Opcode::Div) => DecodedInst::Div {
+            a: get(&RA, word),
+            b: get(&RB, word),
+            c: get(&RC, word),
+        },
+        Ok(Opcode::BNand) => DecodedInst::BNand {
+            a: get(&RA, word),
+            b: get(&RB, word),
+            c: get(&RC, word),
+        },
+        Ok(Opcode::Halt) => DecodedInst::Halt,
+        Ok(Opcode::MapSeg) => DecodedInst::MapSeg {
+            b: get(&RB, word),
+            c: get(&RC, word),
+        },
+        Ok(Opcode::UnmapSeg) => DecodedInst::UnmapSeg { c: get(&RC, word) },
+        Ok(Opcode::Output) => DecodedInst::Output { c: get(&RC, word) },
+        Ok(Opcode::Input) => DecodedInst::Input { c: get(&RC, word) },
+        Ok(Opcode::LoadProg) => DecodedInst::LoadProg {
+            b: get(&RB, word),
+            c: get(&RC, word),
+        },
+        Ok(Opcode::LoadVal) => DecodedInst::LoadVal {
+            location: get(&RL, word),
+            value: get(&VL, word),
+        },
+        Err(_) => DecodedInst::Data(word),
+    }
+}
+
+/// Per-segment pre-decoded instruction cache used by `Engine::PreDecoded`.
+///
+/// Only segment 0 is ever executed, but entries are tracked per segment
+/// id so the cache can grow and invalidate in lockstep with
+/// `State`'s own segment table as `seg_store`/`map_seg`/`unmap_seg`/
+/// `load_prog` mutate it.
+#[derive(Default)]
+pub(crate) struct SegmentCache {
+    entries: Vec<Option<Vec<DecodedInst>>>,
+}
+
+impl SegmentCache {
+    fn ensure_len(&mut self, len: usize) {
+        if self.entries.len() < len {
+            self.entries.resize_with(len, || None);
+        }
+    }
+
+    /// Marks a segment's cached decode as stale; it is lazily re-decoded
+    /// the next time `get_or_decode` is asked for it.
+    pub(crate) fn invalidate(&mut self, seg: usize) {
+        self.ensure_len(seg + 1);
+        self.entries[seg] = None;
+    }
+
+    /// Returns the decoded form of `words`, decoding and caching it if
+    /// this is the first time segment `seg` has been asked for since it
+    /// was last invalidated.
+    pub(crate) fn get_or_decode(&mut self, seg: usize, words: &[u32]) -> &[DecodedInst] {
+        self.ensure_len(seg + 1);
+        if self.entries[seg].is_none() {
+            self.entries[seg] = Some(words.iter().map(|&word| decode_word(word)).collect());
+        }
+        self.entries[seg].as_ref().unwrap()
+    }
+}
+
+/// Fetches, via `state`'s pre-decoded cache, the `DecodedInst` at the
+/// current program counter and dispatches it to the matching `State`
+/// method, advancing the program counter exactly like `rumdecoder::run`'s
+/// interpreter loop does.
+///
+/// <br>
+///
+/// # Argument:
+/// * `state`: The struct representing the state of the UM.
+pub(crate) fn dispatch_decoded(state: &mut State) -> Result<(), Trap> {
+    match state.fetch_decoded()? {
+        DecodedInst::CMov { a, b, c } => {
+            state.cmov(a, b, c);
+            Ok(())
+        }
+        DecodedInst::SegLoad { a, b, c } => state.seg_load(a, b, c),
+        DecodedInst::SegStore { a, b, c } => state.seg_store(a, b, c),
+        DecodedInst::Add { a, b, c } => {
+            state.add(a, b, c);
+            Ok(())
+        }
+        DecodedInst::Mul { a, b, c } => {
+            state.mul(a, b, c);
+            Ok(())
+        }
+        DecodedInst::Div { a, b, c } => state.div(a, b, c),
+        DecodedInst::BNand { a, b, c } => {
+            state.b_nand(a, b, c);
+            Ok(())
+        }
+        DecodedInst::Halt => state.halt(),
+        DecodedInst::MapSeg { b, c } => {
+            state.map_seg(b, c);
+            Ok(())
+        }
+        DecodedInst::UnmapSeg { c } => state.unmap_seg(c),
+        DecodedInst::Output { c } => state.output(c),
+        DecodedInst::Input { c } => {
+            state.input(c);
+            Ok(())
+        }
+        DecodedInst::LoadProg { b, c } => state.load_prog(b, c),
+        DecodedInst::LoadVal { location, value } => {
+            state.load_val(location, value);
+            Ok(())
+        }
+        DecodedInst::Data(word) => Err(Trap::InvalidOpcode(get(&OP, word))),
+    }
+}
@@ -0,0 +1,8 @@
+pub mod debugger;
+pub mod engine;
+pub mod rumasm;
+pub mod rumdecoder;
+pub mod rumdisasm;
+pub mod rumload;
+pub mod states;
+pub mod trap;
@@ -10,5 +10,8 @@ fn main() {
     let instructions = rumload::load(input.as_deref());
     let mut state = State::new();
     state.boot_up_instructions(instructions);
-    rumdecoder::run(&mut state)
+    if let Err(trap) = rumdecoder::run(&mut state) {
+        eprintln!("um: {}", trap);
+        std::process::exit(1);
+    }
 }
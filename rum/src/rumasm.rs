@@ -0,0 +1,245 @@
+use crate::rumdecoder::{pack, Opcode, OP, RA, RB, RC, RL, VL};
+
+/// Largest value that fits in the 25-bit load-value field.
+const MAX_LOAD_VALUE: u32 = (1 << 25) - 1;
+
+/// An error produced while assembling textual UM assembly, carrying the
+/// 1-based line and column of the offending token so a caller can point
+/// a user at the exact spot in their source.
+///
+/// <br>
+///
+/// # Fields
+/// * `line`: The 1-based line number the error occurred on.
+/// * `column`: The 1-based column of the offending token.
+/// * `message`: A human-readable description of the problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for AsmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// Assembles a small mnemonic-per-line UM assembly syntax into the packed
+/// big-endian code words `rumload::load` reads back.
+///
+/// <br>
+///
+/// One instruction is written per line as `mnemonic operand...`, e.g.
+/// `add r1 r2 r3` or `loadval r4 12345`. Blank lines and `#` comments are
+/// ignored.
+///
+/// # Argument:
+/// * `source`: The assembly source text.
+pub fn assemble(source: &str) -> Result<Vec<u32>, AsmError> {
+    let mut words = Vec::new();
+    for (line_no, raw_line) in source.lines().enumerate() {
+        let line_no = line_no + 1;
+        let line = match raw_line.find('#') {
+            Some(idx) => &raw_line[..idx],
+            None => raw_line,
+        };
+        let toks = tokenize(line);
+        if toks.is_empty() {
+            continue;
+        }
+        words.push(assemble_line(line_no, &toks)?);
+    }
+    Ok(words)
+}
+
+/// A single whitespace-separated token together with its 1-based column.
+struct Token<'a> {
+    column: usize,
+    text: &'a str,
+}
+
+fn tokenize(line: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some(&(start, ch)) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut end = start;
+        while let Some(&(idx, c)) = chars.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            end = idx + c.len_utf8();
+            chars.next();
+        }
+        tokens.push(Token {
+            column: start + 1,
+            text: &line[start..end],
+        });
+    }
+    tokens
+}
+
+fn assemble_line(line: usize, toks: &[Token<'_>]) -> Result<u32, AsmError> {
+    let mnemonic = &toks[0];
+    let operands = &toks[1..];
+    match mnemonic.text {
+        "cmov" => three_register(line, mnemonic, operands, Opcode::CMov),
+        "segload" => three_register(line, mnemonic, operands, Opcode::SegLoad),
+        "segstore" => three_register(line, mnemonic, operands, Opcode::SegStore),
+        "add" => three_register(line, mnemonic, operands, Opcode::Add),
+        "mul" => three_register(line, mnemonic, operands, Opcode::Mul),
+        "div" => three_register(line, mnemonic, operands, Opcode::Div),
+        "nand" => three_register(line, mnemonic, operands, Opcode::BNand),
+        "halt" => no_operand(line, mnemonic, operands, Opcode::Halt),
+        "mapseg" => two_register(line, mnemonic, operands, Opcode::MapSeg),
+        "unmapseg" => one_register(line, mnemonic, operands, Opcode::UnmapSeg),
+        "output" => one_register(line, mnemonic, operands, Opcode::Output),
+        "input" => one_register(line, mnemonic, operands, Opcode::Input),
+        "loadprog" => two_register(line, mnemonic, operands, Opcode::LoadProg),
+        "loadval" => load_val(line, mnemonic, operands),
+        other => Err(AsmError {
+            line,
+            column: mnemonic.column,
+            message: format!("unknown mnemonic '{}'", other),
+        }),
+    }
+}
+
+fn register(line: usize, tok: &Token<'_>) -> Result<u32, AsmError> {
+    let digits = tok.text.strip_prefix('r').ok_or_else(|| AsmError {
+        line,
+        column: tok.column,
+        message: format!("expected a register like 'r0', found '{}'", tok.text),
+    })?;
+    let reg: u32 = digits.parse().map_err(|_| AsmError {
+        line,
+        column: tok.column,
+        message: format!("expected a register like 'r0', found '{}'", tok.text),
+    })?;
+    if reg > 7 {
+        return Err(AsmError {
+            line,
+            column: tok.column,
+            message: format!("register '{}' is out of range 0..=7", tok.text),
+        });
+    }
+    Ok(reg)
+}
+
+fn expect_operand_count(
+    line: usize,
+    mnemonic: &Token<'_>,
+    operands: &[Token<'_>],
+    expected: usize,
+) -> Result<(), AsmError> {
+    if operands.len() != expected {
+        return Err(AsmError {
+            line,
+            column: mnemonic.column,
+            message: format!(
+                "'{}' expects {} operand(s), found {}",
+                mnemonic.text,
+                expected,
+                operands.len()
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn no_operand(
+    line: usize,
+    mnemonic: &Token<'_>,
+    operands: &[Token<'_>],
+    op: Opcode,
+) -> Result<u32, AsmError> {
+    expect_operand_count(line, mnemonic, operands, 0)?;
+    Ok(pack(&OP, op.code()))
+}
+
+fn one_register(
+    line: usize,
+    mnemonic: &Token<'_>,
+    operands: &[Token<'_>],
+    op: Opcode,
+) -> Result<u32, AsmError> {
+    expect_operand_count(line, mnemonic, operands, 1)?;
+    let c = register(line, &operands[0])?;
+    Ok(pack(&OP, op.code()) | pack(&RC, c))
+}
+
+fn two_register(
+    line: usize,
+    mnemonic: &Token<'_>,
+    operands: &[Token<'_>],
+    op: Opcode,
+) -> Result<u32, AsmError> {
+    expect_operand_count(line, mnemonic, operands, 2)?;
+    let b = register(line, &operands[0])?;
+    let c = register(line, &operands[1])?;
+    Ok(pack(&OP, op.code()) | pack(&RB, b) | pack(&RC, c))
+}
+
+fn three_register(
+    line: usize,
+    mnemonic: &Token<'_>,
+    operands: &[Token<'_>],
+    op: Opcode,
+) -> Result<u32, AsmError> {
+    expect_operand_count(line, mnemonic, operands, 3)?;
+    let a = register(line, &operands[0])?;
+    let b = register(line, &operands[1])?;
+    let c = register(line, &operands[2])?;
+    Ok(pack(&OP, op.code()) | pack(&RA, a) | pack(&RB, b) | pack(&RC, c))
+}
+
+fn load_val(line: usize, mnemonic: &Token<'_>, operands: &[Token<'_>]) -> Result<u32, AsmError> {
+    expect_operand_count(line, mnemonic, operands, 2)?;
+    let location = register(line, &operands[0])?;
+    let value_tok = &operands[1];
+    let value: u32 = value_tok.text.parse().map_err(|_| AsmError {
+        line,
+        column: value_tok.column,
+        message: format!("expected a numeric load value, found '{}'", value_tok.text),
+    })?;
+    if value > MAX_LOAD_VALUE {
+        return Err(AsmError {
+            line,
+            column: value_tok.column,
+            message: format!("load value {} does not fit in 25 bits", value),
+        });
+    }
+    Ok(pack(&OP, Opcode::LoadVal.code()) | pack(&RL, location) | pack(&VL, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_mnemonic_reports_its_own_column() {
+        let err = assemble("  bogus r0 r1 r2").unwrap_err();
+        assert_eq!(err.line, 1);
+        assert_eq!(err.column, 3);
+        assert!(err.message.contains("unknown mnemonic 'bogus'"));
+    }
+
+    #[test]
+    fn load_value_out_of_25_bits_is_rejected() {
+        let err = assemble("loadval r0 33554432").unwrap_err();
+        assert_eq!(err.message, "load value 33554432 does not fit in 25 bits");
+    }
+
+    #[test]
+    fn load_value_at_the_25_bit_boundary_is_accepted() {
+        let words = assemble("loadval r0 33554431").unwrap();
+        assert_eq!(words.len(), 1);
+    }
+}
@@ -1,4 +1,7 @@
+use crate::engine::{self, Engine};
 use crate::states::State;
+use crate::trap::Trap;
+use std::convert::TryFrom;
 
 /// Represents an instruction for the Universal Machine
 type Umi = u32;
@@ -33,44 +36,97 @@ pub enum Opcode {
     LoadVal,
 }
 
-impl From<u32> for Opcode {
-    fn from(val: u32) -> Self {
+impl TryFrom<u32> for Opcode {
+    type Error = Trap;
+
+    fn try_from(val: u32) -> Result<Self, Trap> {
         match val {
-            0 => Opcode::CMov,
-            1 => Opcode::SegLoad,
-            2 => Opcode::SegStore,
-            3 => Opcode::Add,
-            4 => Opcode::Mul,
-            5 => Opcode::Div,
-            6 => Opcode::BNand,
-            7 => Opcode::Halt,
-            8 => Opcode::MapSeg,
-            9 => Opcode::UnmapSeg,
-            10 => Opcode::Output,
-            11 => Opcode::Input,
-            12 => Opcode::LoadProg,
-            13 => Opcode::LoadVal,
-            _ => panic!("Not valid Opcode"),
+            0 => Ok(Opcode::CMov),
+            1 => Ok(Opcode::SegLoad),
+            2 => Ok(Opcode::SegStore),
+            3 => Ok(Opcode::Add),
+            4 => Ok(Opcode::Mul),
+            5 => Ok(Opcode::Div),
+            6 => Ok(Opcode::BNand),
+            7 => Ok(Opcode::Halt),
+            8 => Ok(Opcode::MapSeg),
+            9 => Ok(Opcode::UnmapSeg),
+            10 => Ok(Opcode::Output),
+            11 => Ok(Opcode::Input),
+            12 => Ok(Opcode::LoadProg),
+            13 => Ok(Opcode::LoadVal),
+            other => Err(Trap::InvalidOpcode(other)),
+        }
+    }
+}
+
+impl Opcode {
+    /// Gives back the numeric Opcode field value this variant decodes from,
+    /// the inverse of `Opcode::try_from`. Used by the assembler to pack a
+    /// mnemonic back into a code word.
+    pub(crate) fn code(&self) -> u32 {
+        match self {
+            Opcode::CMov => 0,
+            Opcode::SegLoad => 1,
+            Opcode::SegStore => 2,
+            Opcode::Add => 3,
+            Opcode::Mul => 4,
+            Opcode::Div => 5,
+            Opcode::BNand => 6,
+            Opcode::Halt => 7,
+            Opcode::MapSeg => 8,
+            Opcode::UnmapSeg => 9,
+            Opcode::Output => 10,
+            Opcode::Input => 11,
+            Opcode::LoadProg => 12,
+            Opcode::LoadVal => 13,
         }
     }
 }
 
 // Register at position A
-static RA: Field = Field { width: 3, lsb: 6 };
+pub(crate) static RA: Field = Field { width: 3, lsb: 6 };
 // Register at position B
-static RB: Field = Field { width: 3, lsb: 3 };
+pub(crate) static RB: Field = Field { width: 3, lsb: 3 };
 // Register at position C
-static RC: Field = Field { width: 3, lsb: 0 };
+pub(crate) static RC: Field = Field { width: 3, lsb: 0 };
 // Register used only on the load value instruction
-static RL: Field = Field { width: 3, lsb: 25 };
+pub(crate) static RL: Field = Field { width: 3, lsb: 25 };
 // Load value field
-static VL: Field = Field { width: 25, lsb: 0 };
+pub(crate) static VL: Field = Field { width: 25, lsb: 0 };
 // Representation of the Opcode
-static OP: Field = Field { width: 4, lsb: 28 };
+pub(crate) static OP: Field = Field { width: 4, lsb: 28 };
 
-pub fn run(state: &mut State) {
+/// Runs `state` to completion, decoding and executing instructions until
+/// `halt` or a fault is reached, using whichever engine `state.engine()`
+/// selects. A clean `halt` is reported as `Ok(())`; any other `Trap` is
+/// returned to the caller instead of panicking or exiting the process.
+///
+/// <br>
+///
+/// # Argument:
+/// * `state`: The struct representing the state of the UM.
+pub fn run(state: &mut State) -> Result<(), Trap> {
+    match state.engine() {
+        Engine::Interpret => run_with(state, decode_inst),
+        Engine::PreDecoded => run_with(state, engine::dispatch_decoded),
+        // The JIT tier uses the pre-decoded dispatcher for every
+        // segment, hot or cold; nothing is compiled to native code.
+        #[cfg(feature = "jit")]
+        Engine::Jit => run_with(state, engine::dispatch_decoded),
+    }
+}
+
+fn run_with(
+    state: &mut State,
+    mut fetch_and_exec: impl FnMut(&mut State) -> Result<(), Trap>,
+) -> Result<(), Trap> {
     loop {
-        decode_inst(state)
+        match fetch_and_exec(state) {
+            Ok(()) => continue,
+            Err(Trap::Halt) => return Ok(()),
+            Err(trap) => return Err(trap),
+        }
     }
 }
 
@@ -82,7 +138,7 @@ pub fn run(state: &mut State) {
 ///
 /// # Argument:
 /// `bits`: the size of the sequence of bits.
-fn mask(bits: u32) -> u32 {
+pub(crate) fn mask(bits: u32) -> u32 {
     (1 << bits) - 1
 }
 
@@ -93,10 +149,23 @@ fn mask(bits: u32) -> u32 {
 /// # Arguments:
 /// * `field`: Representation of the value at some position within the instruction.
 /// * `instruction`: 32-bit code word.
-fn get(field: &Field, instruction: Umi) -> u32 {
+pub(crate) fn get(field: &Field, instruction: Umi) -> u32 {
     (instruction >> field.lsb) & mask(field.width)
 }
 
+/// Packs a value into its field's position within a code word, the
+/// inverse of `get`. Used by the assembler to build an instruction one
+/// field at a time.
+///
+/// <br>
+///
+/// # Arguments:
+/// * `field`: Representation of the value at some position within the instruction.
+/// * `value`: The value to place into that field.
+pub(crate) fn pack(field: &Field, value: u32) -> u32 {
+    (value & mask(field.width)) << field.lsb
+}
+
 /// Decodes the instruction set and calls the appropriate state method to handle the current
 /// executing instruction.
 ///
@@ -104,22 +173,23 @@ fn get(field: &Field, instruction: Umi) -> u32 {
 ///
 /// # Argument:
 /// * `state`: The struct representing the state of the UM.
-fn decode_inst(state: &mut State) {
-    let inst = state.get_instruction();
-    match get(&OP, inst).into() {
+pub(crate) fn decode_inst(state: &mut State) -> Result<(), Trap> {
+    let inst = state.get_instruction()?;
+    match Opcode::try_from(get(&OP, inst))? {
         Opcode::CMov => state.cmov(get(&RA, inst), get(&RB, inst), get(&RC, inst)),
-        Opcode::SegLoad => state.seg_load(get(&RA, inst), get(&RB, inst), get(&RC, inst)),
-        Opcode::SegStore => state.seg_store(get(&RA, inst), get(&RB, inst), get(&RC, inst)),
+        Opcode::SegLoad => state.seg_load(get(&RA, inst), get(&RB, inst), get(&RC, inst))?,
+        Opcode::SegStore => state.seg_store(get(&RA, inst), get(&RB, inst), get(&RC, inst))?,
         Opcode::Add => state.add(get(&RA, inst), get(&RB, inst), get(&RC, inst)),
         Opcode::Mul => state.mul(get(&RA, inst), get(&RB, inst), get(&RC, inst)),
-        Opcode::Div => state.div(get(&RA, inst), get(&RB, inst), get(&RC, inst)),
+        Opcode::Div => state.div(get(&RA, inst), get(&RB, inst), get(&RC, inst))?,
         Opcode::BNand => state.b_nand(get(&RA, inst), get(&RB, inst), get(&RC, inst)),
-        Opcode::Halt => state.halt(),
+        Opcode::Halt => state.halt()?,
         Opcode::MapSeg => state.map_seg(get(&RB, inst), get(&RC, inst)),
-        Opcode::UnmapSeg => state.unmap_seg(get(&RC, inst)),
-        Opcode::Output => state.output(get(&RC, inst)),
+        Opcode::UnmapSeg => state.unmap_seg(get(&RC, inst))?,
+        Opcode::Output => state.output(get(&RC, inst))?,
         Opcode::Input => state.input(get(&RC, inst)),
-        Opcode::LoadProg => state.load_prog(get(&RB, inst), get(&RC, inst)),
+        Opcode::LoadProg => state.load_prog(get(&RB, inst), get(&RC, inst))?,
         Opcode::LoadVal => state.load_val(get(&RL, inst), get(&VL, inst)),
     }
+    Ok(())
 }
@@ -0,0 +1,93 @@
+use crate::rumdecoder::{get, Opcode, OP, RA, RB, RC, RL, VL};
+use std::convert::TryFrom;
+
+/// Renders a whole instruction stream as `offset: mnemonic operands`
+/// lines, one per code word.
+///
+/// <br>
+///
+/// # Argument:
+/// * `words`: The code words to render, e.g. as loaded by `rumload::load`.
+pub fn disassemble(words: &[u32]) -> String {
+    let mut out = String::new();
+    for (offset, &word) in words.iter().enumerate() {
+        out.push_str(&format!("{:04x}: {}\n", offset, disassemble_one(word)));
+    }
+    out
+}
+
+/// Renders a single code word as `mnemonic operands`, using the same
+/// mnemonics `rumasm::assemble` accepts. A word whose Opcode field does
+/// not name one of the fourteen instructions is rendered as
+/// `.data 0x...` rather than panicking.
+///
+/// <br>
+///
+/// # Argument:
+/// * `word`: The 32-bit code word to render.
+pub fn disassemble_one(word: u32) -> String {
+    let opcode = match Opcode::try_from(get(&OP, word)) {
+        Ok(opcode) => opcode,
+        Err(_) => return format!(".data 0x{:08x}", word),
+    };
+    match opcode {
+        Opcode::CMov => three_register("cmov", word),
+        Opcode::SegLoad => three_register("segload", word),
+        Opcode::SegStore => three_register("segstore", word),
+        Opcode::Add => three_register("add", word),
+        Opcode::Mul => three_register("mul", word),
+        Opcode::Div => three_register("div", word),
+        Opcode::BNand => three_register("nand", word),
+        Opcode::Halt => "halt".to_string(),
+        Opcode::MapSeg => format!("mapseg r{} r{}", get(&RB, word), get(&RC, word)),
+        Opcode::UnmapSeg => format!("unmapseg r{}", get(&RC, word)),
+        Opcode::Output => format!("output r{}", get(&RC, word)),
+        Opcode::Input => format!("input r{}", get(&RC, word)),
+        Opcode::LoadProg => format!("loadprog r{} r{}", get(&RB, word), get(&RC, word)),
+        Opcode::LoadVal => format!("loadval r{} {}", get(&RL, word), get(&VL, word)),
+    }
+}
+
+fn three_register(mnemonic: &str, word: u32) -> String {
+    format!(
+        "{} r{} r{} r{}",
+        mnemonic,
+        get(&RA, word),
+        get(&RB, word),
+        get(&RC, word)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rumasm::assemble;
+
+    #[test]
+    fn assemble_disassemble_round_trip() {
+        let source = "cmov r1 r2 r3\n\
+                       segload r1 r2 r3\n\
+                       segstore r1 r2 r3\n\
+                       add r1 r2 r3\n\
+                       mul r1 r2 r3\n\
+                       div r1 r2 r3\n\
+                       nand r1 r2 r3\n\
+                       halt\n\
+                       mapseg r2 r3\n\
+                       unmapseg r3\n\
+                       output r3\n\
+                       input r3\n\
+                       loadprog r2 r3\n\
+                       loadval r4 12345\n";
+        let words = assemble(source).unwrap();
+        let rendered: Vec<String> = words.iter().map(|&word| disassemble_one(word)).collect();
+        let expected: Vec<&str> = source.lines().collect();
+        assert_eq!(rendered, expected);
+        assert_eq!(assemble(&rendered.join("\n")).unwrap(), words);
+    }
+
+    #[test]
+    fn unknown_opcode_disassembles_as_data() {
+        assert_eq!(disassemble_one(0xf000_0000), ".data 0xf0000000");
+    }
+}
@@ -1,3 +1,5 @@
+use crate::engine::{DecodedInst, Engine, SegmentCache};
+use crate::trap::Trap;
 use std::io::{stdin, Read};
 
 /// The States struct is used to represent each component of the Universal Machine.
@@ -11,6 +13,18 @@ pub struct State {
     allocated_memory: Vec<Vec<u32>>,
     freed_memory: Vec<u32>,
     prog_counter: usize,
+    instruction_count: u64,
+    budget: Option<u64>,
+    wrap_period: Option<u64>,
+    on_wrap: Option<Box<dyn FnMut(u64)>>,
+    engine: Engine,
+    decoded_cache: SegmentCache,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl State {
@@ -21,7 +35,119 @@ impl State {
             allocated_memory: Vec::new(),
             freed_memory: Vec::new(),
             prog_counter: 0,
+            instruction_count: 0,
+            budget: None,
+            wrap_period: None,
+            on_wrap: None,
+            engine: Engine::default(),
+            decoded_cache: SegmentCache::default(),
+        }
+    }
+
+    /// Gives back which execution engine `rumdecoder::run` will use.
+    pub fn engine(&self) -> Engine {
+        self.engine
+    }
+
+    /// Selects which execution engine `rumdecoder::run` uses: re-decode
+    /// every instruction (`Engine::Interpret`) or dispatch from a
+    /// pre-decoded cache (`Engine::PreDecoded`). Switching to
+    /// `Engine::PreDecoded` invalidates segment 0's cache so it is
+    /// decoded fresh on the next fetch.
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+        if engine == Engine::PreDecoded {
+            self.decoded_cache.invalidate(0);
+        }
+    }
+
+    /// Gives back how many instructions have been fetched since the last
+    /// reset (or wrap-around, if wrapping is enabled).
+    pub fn instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+
+    /// Resets the instruction counter back to zero.
+    pub fn reset_instruction_count(&mut self) {
+        self.instruction_count = 0;
+    }
+
+    /// Sets (or clears, with `None`) the instruction budget. Once the
+    /// counter exceeds the budget, `get_instruction` fails with
+    /// `Trap::BudgetExhausted` instead of fetching another instruction.
+    pub fn set_budget(&mut self, budget: Option<u64>) {
+        self.budget = budget;
+    }
+
+    /// Enables wrap-around mode: once the instruction counter reaches
+    /// `period`, `callback` is invoked with the counter value and the
+    /// counter is reset to zero. Useful for periodic I/O flushing or
+    /// profiling over long-running programs.
+    ///
+    /// <br>
+    ///
+    /// # Argument:
+    /// * `period`: The number of instructions between wraps.
+    /// * `callback`: Invoked with the counter value each time it wraps.
+    pub fn set_wrap(&mut self, period: u64, callback: impl FnMut(u64) + 'static) {
+        self.wrap_period = Some(period);
+        self.on_wrap = Some(Box::new(callback));
+    }
+
+    /// Disables wrap-around mode set with `set_wrap`.
+    pub fn clear_wrap(&mut self) {
+        self.wrap_period = None;
+        self.on_wrap = None;
+    }
+
+    /// Increments the instruction counter and applies the budget/wrap
+    /// policy, shared by every path that fetches an instruction.
+    fn bump_instruction_count(&mut self) -> Result<(), Trap> {
+        self.instruction_count = self.instruction_count.wrapping_add(1);
+
+        if let Some(budget) = self.budget {
+            if self.instruction_count > budget {
+                return Err(Trap::BudgetExhausted);
+            }
+        }
+
+        if let Some(period) = self.wrap_period {
+            if self.instruction_count >= period {
+                if let Some(on_wrap) = self.on_wrap.as_mut() {
+                    on_wrap(self.instruction_count);
+                }
+                self.instruction_count = 0;
+            }
         }
+
+        Ok(())
+    }
+
+    /// Gives back the current value of the register at the given index.
+    ///
+    /// <br>
+    ///
+    /// # Argument:
+    /// * `index`: The index of the register to read.
+    pub fn register(&self, index: u32) -> u32 {
+        self.registers[index as usize]
+    }
+
+    /// Gives back the program counter: the index of the next instruction
+    /// to be fetched from segment 0.
+    pub fn prog_counter(&self) -> usize {
+        self.prog_counter
+    }
+
+    /// Gives back the words of the segment with the given id, or `None`
+    /// if that id has never been mapped.
+    ///
+    /// <br>
+    ///
+    /// # Argument:
+    /// * `seg`: The segment id to read.
+    pub fn segment(&self, seg: usize) -> Option<&[u32]> {
+        self.allocated_memory.get(seg).map(Vec::as_slice)
     }
 
     /// Adds the instruction set to the state object representing the UM state.
@@ -37,13 +163,35 @@ impl State {
     /// Gets the instruction that should be decoded at the current point in time in the UM.
     /// After the instruction is extracted, the program counter is to the next instruction id.
     /// Returns the instruction as a code word represented as u32.
-    pub unsafe fn get_instruction(&mut self) -> u32 {
-        let inst = self
+    pub fn get_instruction(&mut self) -> Result<u32, Trap> {
+        let segment = self
             .allocated_memory
-            .get_unchecked(0)
-            .get_unchecked(self.prog_counter);
+            .first()
+            .ok_or(Trap::UnmappedSegment)?;
+        let inst = *segment
+            .get(self.prog_counter)
+            .ok_or(Trap::SegLoadOutOfBounds)?;
         self.prog_counter += 1;
-        return *inst;
+        self.bump_instruction_count()?;
+        Ok(inst)
+    }
+
+    /// Fetches the `DecodedInst` at the current program counter from
+    /// segment 0's pre-decoded cache, decoding and caching the segment
+    /// first if it is not already cached. Used by `Engine::PreDecoded`
+    /// in place of `get_instruction`.
+    pub(crate) fn fetch_decoded(&mut self) -> Result<DecodedInst, Trap> {
+        let segment = self
+            .allocated_memory
+            .first()
+            .ok_or(Trap::UnmappedSegment)?;
+        if self.prog_counter >= segment.len() {
+            return Err(Trap::SegLoadOutOfBounds);
+        }
+        let inst = self.decoded_cache.get_or_decode(0, segment)[self.prog_counter];
+        self.prog_counter += 1;
+        self.bump_instruction_count()?;
+        Ok(inst)
     }
 
     /// This functions represents a Conditional Move, which the UM identifies as Opcode 0.
@@ -73,11 +221,16 @@ impl State {
     /// * `b`: The index of the register at position B.
     /// * `c`: The index of the register at position C.
     /// * `state`: The struct representing the current state of the UM.
-    pub unsafe fn seg_load(&mut self, a: u32, b: u32, c: u32) {
-        self.registers[a as usize] = *self
+    pub fn seg_load(&mut self, a: u32, b: u32, c: u32) -> Result<(), Trap> {
+        let segment = self
             .allocated_memory
-            .get_unchecked(self.registers[b as usize] as usize)
-            .get_unchecked(self.registers[c as usize] as usize)
+            .get(self.registers[b as usize] as usize)
+            .ok_or(Trap::UnmappedSegment)?;
+        let value = *segment
+            .get(self.registers[c as usize] as usize)
+            .ok_or(Trap::SegLoadOutOfBounds)?;
+        self.registers[a as usize] = value;
+        Ok(())
     }
 
     /// Stores the value of the register at position C inside the allocated memory at row as
@@ -90,9 +243,18 @@ impl State {
     /// * `b`: The index of the register at position B.
     /// * `c`: The index of the register at position C.
     /// * `state`: The struct representing the current state of the UM.
-    pub unsafe fn seg_store(&mut self, a: u32, b: u32, c: u32) {
-        self.allocated_memory[self.registers[a as usize] as usize]
-            [self.registers[b as usize] as usize] = self.registers[c as usize]
+    pub fn seg_store(&mut self, a: u32, b: u32, c: u32) -> Result<(), Trap> {
+        let seg_id = self.registers[a as usize] as usize;
+        let segment = self
+            .allocated_memory
+            .get_mut(seg_id)
+            .ok_or(Trap::UnmappedSegment)?;
+        let slot = segment
+            .get_mut(self.registers[b as usize] as usize)
+            .ok_or(Trap::SegLoadOutOfBounds)?;
+        *slot = self.registers[c as usize];
+        self.decoded_cache.invalidate(seg_id);
+        Ok(())
     }
 
     /// Adds the value of the register at position B plus the value of the register at position C.
@@ -135,9 +297,13 @@ impl State {
     /// * `b`: The index of the register at position B.
     /// * `c`: The index of the register at position C.
     /// * `state`: The struct representing the current state of the UM.
-    pub fn div(&mut self, a: u32, b: u32, c: u32) {
-        self.registers[a as usize] =
-            self.registers[b as usize].wrapping_div(self.registers[c as usize])
+    pub fn div(&mut self, a: u32, b: u32, c: u32) -> Result<(), Trap> {
+        let divisor = self.registers[c as usize];
+        if divisor == 0 {
+            return Err(Trap::DivByZero);
+        }
+        self.registers[a as usize] = self.registers[b as usize].wrapping_div(divisor);
+        Ok(())
     }
 
     /// Computes the result of the bitwise negation of the value in the register at position B bitwise
@@ -154,9 +320,10 @@ impl State {
         self.registers[a as usize] = !(self.registers[b as usize] & self.registers[c as usize])
     }
 
-    /// Terminates the execution of the UM. Prints exit code 0 to standard out.
-    pub fn halt(&self) {
-        std::process::exit(0)
+    /// Terminates the execution of the UM by returning `Trap::Halt`,
+    /// which `rumdecoder::run` treats as a clean, non-error stop.
+    pub fn halt(&self) -> Result<(), Trap> {
+        Err(Trap::Halt)
     }
 
     /// Creates a new allocation of words equal to the value been hold in the register at position
@@ -173,6 +340,7 @@ impl State {
         match self.freed_memory.pop() {
             Some(idx) => {
                 self.allocated_memory[idx as usize] = new_allocation;
+                self.decoded_cache.invalidate(idx as usize);
                 self.registers[b as usize] = idx;
             }
             None => {
@@ -190,10 +358,15 @@ impl State {
     /// # Argument:
     /// * `c`: The index of the register at position C.
     /// * `state`: The struct representing the current state of the UM.
-    pub fn unmap_seg(&mut self, c: u32) {
+    pub fn unmap_seg(&mut self, c: u32) -> Result<(), Trap> {
         let freed_location = self.registers[c as usize];
-        self.allocated_memory[freed_location as usize].clear();
-        self.freed_memory.push(freed_location)
+        self.allocated_memory
+            .get_mut(freed_location as usize)
+            .ok_or(Trap::UnmappedSegment)?
+            .clear();
+        self.decoded_cache.invalidate(freed_location as usize);
+        self.freed_memory.push(freed_location);
+        Ok(())
     }
 
     /// Takes the values in the register at position C, and displays the value in
@@ -204,10 +377,14 @@ impl State {
     /// # Argument:
     /// * `c`: The index of the register at position C.
     /// * `state`: The struct representing the current state of the UM.///
-    pub fn output(&mut self, c: u32) {
-        match u8::try_from(self.registers[c as usize]) {
-            Ok(val) => print!("{}", val as char),
-            Err(error) => panic!("Value is not in range {:?}", error),
+    pub fn output(&mut self, c: u32) -> Result<(), Trap> {
+        let value = self.registers[c as usize];
+        match u8::try_from(value) {
+            Ok(val) => {
+                print!("{}", val as char);
+                Ok(())
+            }
+            Err(_) => Err(Trap::OutputOutOfRange(value)),
         }
     }
 
@@ -219,7 +396,7 @@ impl State {
     /// * `c`: The index of the register at position C.
     /// * `state`: The struct representing the current state of the UM.
     pub fn input(&mut self, c: u32) {
-        match stdin().bytes().next() {
+        match stdin().lock().bytes().next() {
             Some(input) => self.registers[c as usize] = input.unwrap() as u32,
             None => self.registers[c as usize] = !0_u32,
         }
@@ -233,14 +410,19 @@ impl State {
     /// * `b`: The index of the register at position B.
     /// * `c`: The index of the register at position C.
     /// * `state`: The struct representing the current state of the UM.
-    pub unsafe fn load_prog(&mut self, b: u32, c: u32) {
+    pub fn load_prog(&mut self, b: u32, c: u32) -> Result<(), Trap> {
         let location = self.registers[b as usize] as usize;
-        if location == 0 {
-            self.prog_counter = self.registers[c as usize] as usize;
-        } else {
-            self.allocated_memory[0] = self.allocated_memory[location].clone();
-            self.prog_counter = self.registers[c as usize] as usize;
+        if location != 0 {
+            let segment = self
+                .allocated_memory
+                .get(location)
+                .ok_or(Trap::UnmappedSegment)?
+                .clone();
+            self.allocated_memory[0] = segment;
+            self.decoded_cache.invalidate(0);
         }
+        self.prog_counter = self.registers[c as usize] as usize;
+        Ok(())
     }
 
     /// Load a pre-define value in the register at position location.
@@ -251,7 +433,36 @@ impl State {
     /// * `location`: The register where the value should be stored.
     /// * `val`: The value to store.
     /// * `state`: The struct representing the current state of the UM.
-    pub unsafe fn load_val(&mut self, location: u32, val: u32) {
+    pub fn load_val(&mut self, location: u32, val: u32) {
         self.registers[location as usize] = val
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn div_by_zero_traps() {
+        let mut state = State::new();
+        state.load_val(1, 10);
+        state.load_val(2, 0);
+        assert_eq!(state.div(0, 1, 2), Err(Trap::DivByZero));
+    }
+
+    #[test]
+    fn output_out_of_range_traps() {
+        let mut state = State::new();
+        state.load_val(0, 256);
+        assert_eq!(state.output(0), Err(Trap::OutputOutOfRange(256)));
+    }
+
+    #[test]
+    fn budget_exhausted_traps_once_exceeded() {
+        let mut state = State::new();
+        state.boot_up_instructions(vec![0; 3]);
+        state.set_budget(Some(1));
+        assert!(state.get_instruction().is_ok());
+        assert_eq!(state.get_instruction(), Err(Trap::BudgetExhausted));
+    }
+}
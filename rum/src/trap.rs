@@ -0,0 +1,42 @@
+use std::fmt;
+
+/// A reason the Universal Machine stopped executing. `Halt` is a clean
+/// stop; every other variant is a fault the embedder can react to
+/// instead of the process dying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trap {
+    /// The `halt` instruction executed.
+    Halt,
+    /// The Opcode field did not name one of the fourteen instructions.
+    InvalidOpcode(u32),
+    /// `div` was asked to divide by a register holding zero.
+    DivByZero,
+    /// `output` was asked to print a register value outside `0..=255`.
+    OutputOutOfRange(u32),
+    /// A segment access indexed beyond the end of the segment.
+    SegLoadOutOfBounds,
+    /// A segment id lay past every segment ever mapped. A segment freed
+    /// by `unmap_seg` keeps its id as an empty segment, so accessing it
+    /// reports `SegLoadOutOfBounds` instead.
+    UnmappedSegment,
+    /// The instruction counter exceeded the budget set with `State::set_budget`.
+    BudgetExhausted,
+}
+
+impl fmt::Display for Trap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trap::Halt => write!(f, "halted"),
+            Trap::InvalidOpcode(op) => write!(f, "invalid opcode: {}", op),
+            Trap::DivByZero => write!(f, "division by zero"),
+            Trap::OutputOutOfRange(val) => {
+                write!(f, "output value {} is not in range 0..=255", val)
+            }
+            Trap::SegLoadOutOfBounds => write!(f, "segment access out of bounds"),
+            Trap::UnmappedSegment => write!(f, "segment is not mapped"),
+            Trap::BudgetExhausted => write!(f, "instruction budget exhausted"),
+        }
+    }
+}
+
+impl std::error::Error for Trap {}